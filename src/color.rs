@@ -0,0 +1,91 @@
+//! TTY-aware colorized diagnostic output.
+//!
+//! A program registers `coloropt` and resolves the parsed flag with
+//! `resolve` to get a `Color`, then calls `error`/`warning`/`note` on it to
+//! print ANSI-styled, prefixed lines to stderr. `Color::Auto` -- the
+//! default -- colorizes only when stderr is attached to a terminal, and the
+//! `NO_COLOR`/`TERM=dumb` environment conventions disable color outright
+//! regardless of the flag.
+
+use getopts::{Matches, Options};
+use std::old_io;
+use std::os;
+
+extern {
+    fn isatty(fd: i32) -> i32;
+}
+
+const STDERR_FILENO: i32 = 2;
+
+/// When to emit ANSI color escapes around diagnostic output.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// Colorize only when stderr is attached to a terminal.
+    Auto,
+    /// Always colorize, regardless of whether stderr is a terminal.
+    Always,
+    /// Never colorize.
+    Never,
+}
+
+impl Color {
+    /// Print a red `error:`-prefixed line to stderr.
+    pub fn error(&self, msg: &str) {
+        self.emit("error", "1;31", msg)
+    }
+
+    /// Print a yellow `warning:`-prefixed line to stderr.
+    pub fn warning(&self, msg: &str) {
+        self.emit("warning", "1;33", msg)
+    }
+
+    /// Print a cyan `note:`-prefixed line to stderr.
+    pub fn note(&self, msg: &str) {
+        self.emit("note", "1;36", msg)
+    }
+
+    fn emit(&self, prefix: &str, ansi: &str, msg: &str) {
+        let line = if self.should_colorize() {
+            format!("\x1b[{}m{}\x1b[0m: {}\n", ansi, prefix, msg)
+        } else {
+            format!("{}: {}\n", prefix, msg)
+        };
+        let _ = old_io::stderr().write_str(&line[]);
+    }
+
+    fn should_colorize(&self) -> bool {
+        match *self {
+            Color::Always => true,
+            Color::Never => false,
+            Color::Auto => stderr_is_tty(),
+        }
+    }
+}
+
+fn stderr_is_tty() -> bool {
+    unsafe { isatty(STDERR_FILENO) != 0 }
+}
+
+/// Add a `--color` flag to `Options`, accepting `auto` (the default),
+/// `always`, or `never`.
+pub fn coloropt(opts: &mut Options) -> &mut Options {
+    opts.optopt("", "color", "Colorize output: auto, always, or never", "WHEN")
+}
+
+/// Resolve the parsed `--color` flag (as registered by `coloropt`) into a
+/// `Color`.
+///
+/// The `NO_COLOR` and `TERM=dumb` environment conventions take precedence
+/// over the flag, disabling color outright whenever output has been
+/// redirected to something that won't render escapes sensibly. An absent or
+/// unrecognized flag value resolves to `Color::Auto`.
+pub fn resolve(matches: &Matches) -> Color {
+    if os::getenv("NO_COLOR").is_some() || os::getenv("TERM").map_or(false, |t| t == "dumb") {
+        return Color::Never;
+    }
+    match matches.opt_str("color").as_ref().map(|s| &s[]) {
+        Some("always") => Color::Always,
+        Some("never") => Color::Never,
+        _ => Color::Auto,
+    }
+}
@@ -32,14 +32,12 @@
 //!         cli::versionopt(&mut opts);
 //!         opts.optopt("o", "", "Set output file name", "FILENAME");
 //!
-//!         let matches = cli::parse_args(&opts);
+//!         let matches = match cli::parse_args(&opts) {
+//!             Some(matches) => matches,
+//!             None => return, // bad flags; usage was printed and exit status set
+//!         };
 //!
-//!         if matches.opt_present("h") {
-//!             println!("{}", cli::usage_string(&opts));
-//!             return;
-//!         }
-//!         if matches.opt_present("version") {
-//!             println!("{}", cli::version_string("0.0.1"));
+//!         if cli::print_help_or_version(&opts, &matches, "0.0.1") {
 //!             return;
 //!         }
 //!
@@ -70,10 +68,16 @@
 #![feature(path)]
 
 extern crate getopts;
+extern crate time;
 use getopts::{Matches, Options};
 use std::{old_io, os};
 use std::old_io::fs;
 
+pub mod color;
+pub mod timer;
+pub mod unstable;
+pub mod verbosity;
+
 mod test;
 
 /// A collection of predefined exit codes cribbed from
@@ -143,7 +147,8 @@ pub fn exec_path() -> Path {
     fs::readlink(&path).unwrap_or(path)
 }
 
-/// Construct a canonical usage string from a collection of `Options`.
+/// Construct a canonical usage string from a collection of `Options`,
+/// naming the running program as in `exec_path`.
 ///
 /// Usage string format:
 ///
@@ -155,8 +160,16 @@ pub fn exec_path() -> Path {
 /// ```
 pub fn usage_string(opts: &Options) -> String {
     let exec_path = exec_path();
-    let exec_path = exec_path.as_str().unwrap_or_else(|| "");
-    format!("{}", opts.usage(&opts.short_usage(exec_path)[]))
+    usage_string_for(opts, exec_path.as_str().unwrap_or_else(|| ""))
+}
+
+/// Construct a canonical usage string from a collection of `Options`,
+/// explicitly naming `program` rather than resolving `exec_path`.
+///
+/// Useful for subcommand-style programs, where the usage string for a verb
+/// should be prefixed with `<program> <verb>` instead of just `<program>`.
+pub fn usage_string_for(opts: &Options, program: &str) -> String {
+    format!("{}", opts.usage(&opts.short_usage(program)[]))
 }
 
 /// Construct a version string.
@@ -170,33 +183,133 @@ pub fn usage_string(opts: &Options) -> String {
 /// <program name> version <version>
 /// ```
 pub fn version_string(version: &str) -> String {
-    format!("{} version {}", exec_path().display(), version)
+    version_string_for(version, &exec_path().display().to_string()[])
+}
+
+/// Construct a version string, explicitly naming `program` rather than
+/// resolving `exec_path`.
+pub fn version_string_for(version: &str, program: &str) -> String {
+    format!("{} version {}", program, version)
+}
+
+/// Write `msg` to stderr and set the process exit status to `code`.
+///
+/// Lets programs map error classes onto the `sysexits` constants without
+/// resorting to `panic!`. This function does not itself halt execution, so
+/// callers should return from `main` promptly afterward in order for the
+/// process to actually exit with the configured status.
+///
+/// # Example
+///
+/// ```ignore
+/// if !input_path.exists() {
+///     cli::die(cli::sysexits::NO_INPUT, "no such file");
+///     return;
+/// }
+/// ```
+pub fn die(code: isize, msg: &str) {
+    let _ = old_io::stderr().write_str(&format!("{}\n", msg)[]);
+    os::set_exit_status(code);
 }
 
 /// Parse the command-line arguments with which the program was executed
 /// according to a collection of `Options`.
 ///
-/// Any flag parsing failure results in task panic. The program's usage string
-/// is printed to stderr prior to panic. Panic is induced in order to avoid
-/// program execution with undefined configuration. In such cases, the presence
-/// of unrecognized flags or invalid flag values implies confusion on the part
-/// of the executor. While perhaps overbearing, it is preferable to halt
-/// execution abruptly than to continue with the risk of unwanted behavior.
-pub fn parse_args(opts: &Options) -> Matches {
-    match opts.parse(os::args().tail()) {
-        Ok(matches) => matches,
+/// A thin wrapper over `parse_args_from` that supplies `os::args().tail()`
+/// as the argument iterator. See `parse_args_from` for failure behavior.
+pub fn parse_args(opts: &Options) -> Option<Matches> {
+    parse_args_from(opts, os::args().into_iter().skip(1))
+}
+
+/// Parse `args` according to a collection of `Options`.
+///
+/// Accepting an arbitrary iterator of arguments -- rather than always
+/// reading `os::args()` -- lets callers exercise `Options` parsing from
+/// tests and lets `dispatch` hand off the remaining arguments after peeling
+/// off a subcommand verb.
+///
+/// On a flag-parsing failure, the program's usage string is written to
+/// stderr and the exit status is set to `sysexits::USAGE` via `die`, rather
+/// than inducing task panic. `None` is returned in that case; callers should
+/// return from `main` immediately so that the process exits cleanly instead
+/// of surfacing what looks like a crash.
+pub fn parse_args_from<I: Iterator<Item=String>>(opts: &Options, args: I) -> Option<Matches> {
+    let exec_path = exec_path();
+    parse_args_from_named(opts, exec_path.as_str().unwrap_or_else(|| ""), args)
+}
+
+/// Parse `args` according to a collection of `Options`, using `program` as
+/// the name embedded in the usage string printed on a parse failure rather
+/// than resolving `exec_path`.
+///
+/// This is what `dispatch` uses internally, so that a subcommand's own
+/// flag-parse failures report e.g. "`cargo build`" instead of just
+/// "`cargo`". See `parse_args_from` for the general case and full failure
+/// behavior.
+pub fn parse_args_from_named<I: Iterator<Item=String>>(
+    opts: &Options,
+    program: &str,
+    args: I
+) -> Option<Matches> {
+    let args: Vec<String> = args.collect();
+    match opts.parse(&args[]) {
+        Ok(matches) => Some(matches),
         Err(getopts_error) => {
-            // Write usage string to stderr, then panic.
-            match old_io::stderr().write_str(&usage_string(opts)[]) {
-                Ok(()) => panic!(getopts_error.to_string()),
-                Err(write_error) =>
-                    // Write to stderr failed -- panic with both error messages.
-                    panic!("{}\n{}", getopts_error.to_string(), write_error.to_string())
-            }
+            die(sysexits::USAGE, &format!("{}\n\n{}", getopts_error, usage_string_for(opts, program))[]);
+            None
         }
     }
 }
 
+/// Check `matches` for the help flag (as registered by `helpopt`) and, if
+/// present, print the usage string and set the exit status to
+/// `sysexits::OK`.
+///
+/// Returns `true` if a message was printed, in which case the caller should
+/// return from `main` immediately so that the process exits with status `0`.
+pub fn print_help(opts: &Options, matches: &Matches) -> bool {
+    if matches.opt_present("h") {
+        println!("{}", usage_string(opts));
+        os::set_exit_status(sysexits::OK);
+        true
+    } else {
+        false
+    }
+}
+
+/// Check `matches` for the version flag (as registered by `versionopt`)
+/// and, if present, print the version string and set the exit status to
+/// `sysexits::OK`.
+///
+/// Returns `true` if a message was printed, in which case the caller should
+/// return from `main` immediately so that the process exits with status `0`.
+pub fn print_version(matches: &Matches, version: &str) -> bool {
+    if matches.opt_present("version") {
+        println!("{}", version_string(version));
+        os::set_exit_status(sysexits::OK);
+        true
+    } else {
+        false
+    }
+}
+
+/// Convenience wrapper combining `print_help` and `print_version`.
+///
+/// Returns `true` if a message was printed, in which case the caller should
+/// return from `main` immediately so that the process exits with status `0`.
+///
+/// # Panics
+///
+/// Requires that the caller registered *both* `helpopt` and `versionopt` on
+/// `opts`. Whenever `-h` is absent, `print_version` is called and asks
+/// `matches.opt_present("version")`, and `getopts::Matches::opt_present`
+/// panics when asked about a flag name that was never registered. A program that only wants one of the two must call
+/// `print_help` or `print_version` directly instead of reaching for this
+/// wrapper.
+pub fn print_help_or_version(opts: &Options, matches: &Matches, version: &str) -> bool {
+    print_help(opts, matches) || print_version(matches, version)
+}
+
 /// Add a help flag to `Options`.
 ///
 /// The flag added is an optional long option for the input `-h`
@@ -217,3 +330,77 @@ pub fn versionopt(opts: &mut Options) -> &mut Options {
         &format!("Print the version of {} being run", exec_path().display())[]
     )
 }
+
+/// A named subcommand, paired with the `Options` used to parse its
+/// arguments, as registered with `dispatch`.
+pub type Subcommand<'a> = (&'a str, &'a Options);
+
+/// Parse `args`, treating the first argument as a subcommand verb and
+/// dispatching the remaining arguments to the matching entry in
+/// `subcommands`.
+///
+/// The verb must come first: options given before it are not skipped, so
+/// a leading option is looked up as a verb and rejected as unknown.
+///
+/// On success, returns the matched verb's name together with its parsed
+/// `Matches`. If no verb is given, or it does not match any registered
+/// subcommand, a top-level usage string listing the available verbs is
+/// written to stderr, the exit status is set to `sysexits::USAGE` via `die`,
+/// and `None` is returned.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut build_opts = Options::new();
+/// let mut test_opts = Options::new();
+/// let subcommands = [("build", &build_opts), ("test", &test_opts)];
+///
+/// match cli::dispatch("cargo", &subcommands, os::args().into_iter().skip(1)) {
+///     Some(("build", matches)) => { /* ... */ },
+///     Some(("test", matches)) => { /* ... */ },
+///     Some((verb, _)) => unreachable!("unregistered verb {}", verb),
+///     None => return,
+/// }
+/// ```
+pub fn dispatch<'a, I: Iterator<Item=String>>(
+    program: &str,
+    subcommands: &[Subcommand<'a>],
+    mut args: I
+) -> Option<(&'a str, Matches)> {
+    match args.next() {
+        Some(ref verb) => match subcommands.iter().find(|&&(name, _)| name == &verb[]) {
+            Some(&(name, opts)) => {
+                let qualified = format!("{} {}", program, name);
+                parse_args_from_named(opts, &qualified[], args).map(|matches| (name, matches))
+            },
+            None => {
+                die(sysexits::USAGE, &dispatch_usage_string(program, subcommands)[]);
+                None
+            }
+        },
+        None => {
+            die(sysexits::USAGE, &dispatch_usage_string(program, subcommands)[]);
+            None
+        }
+    }
+}
+
+/// Construct a top-level usage string listing the verbs registered in
+/// `subcommands`.
+///
+/// Usage string format:
+///
+/// ```ignore
+/// Usage: <program name> <command> [args]
+///
+/// Commands:
+///     verb1
+///     verb2
+/// ```
+pub fn dispatch_usage_string(program: &str, subcommands: &[Subcommand]) -> String {
+    let mut listing = format!("Usage: {} <command> [args]\n\nCommands:\n", program);
+    for &(name, _) in subcommands.iter() {
+        listing.push_str(&format!("    {}\n", name)[]);
+    }
+    listing
+}
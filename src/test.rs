@@ -0,0 +1,217 @@
+use getopts::Options;
+use super::color::{self, Color};
+use super::sysexits;
+use super::timer::{self, Timer};
+use super::unstable::{self, UnstableOpts};
+use super::verbosity::{self, Verbosity};
+
+#[test]
+fn sysexits_ok_is_zero() {
+    assert_eq!(sysexits::OK, 0);
+}
+
+#[test]
+fn sysexits_usage_is_sixty_four() {
+    assert_eq!(sysexits::USAGE, 64);
+}
+
+#[test]
+fn print_help_does_not_require_versionopt() {
+    // A program that only registers `helpopt` (never `versionopt`) must be
+    // able to call `print_help` without `opt_present` panicking over an
+    // unregistered "version" flag.
+    let mut opts = Options::new();
+    super::helpopt(&mut opts);
+    let matches = opts.parse(&["-h".to_string()][]).unwrap();
+
+    assert!(super::print_help(&opts, &matches));
+}
+
+#[test]
+#[should_panic]
+fn print_help_or_version_requires_both_registrars() {
+    // Documents the precondition in `print_help_or_version`'s doc comment:
+    // it is NOT safe to call with only `helpopt` registered, even on
+    // ordinary argv with no flags present at all, because `print_version`
+    // unconditionally asks `opt_present("version")`. Programs that only
+    // want `-h` must call `print_help` directly instead.
+    let mut opts = Options::new();
+    super::helpopt(&mut opts);
+    let matches = opts.parse(&[][]).unwrap();
+
+    super::print_help_or_version(&opts, &matches, "0.0.1");
+}
+
+#[test]
+fn parse_args_from_accepts_valid_flags() {
+    let mut opts = Options::new();
+    opts.optopt("o", "", "Set output file name", "FILENAME");
+
+    let args = vec!["-o".to_string(), "out.txt".to_string()];
+    let matches = super::parse_args_from(&opts, args.into_iter()).unwrap();
+
+    assert_eq!(matches.opt_str("o"), Some("out.txt".to_string()));
+}
+
+#[test]
+fn dispatch_finds_registered_verb() {
+    let mut build_opts = Options::new();
+    build_opts.optflag("", "release", "Build in release mode");
+    let test_opts = Options::new();
+    let subcommands = [("build", &build_opts), ("test", &test_opts)];
+
+    let args = vec!["build".to_string(), "--release".to_string()];
+    let (verb, matches) = super::dispatch("cargo", &subcommands, args.into_iter()).unwrap();
+
+    assert_eq!(verb, "build");
+    assert!(matches.opt_present("release"));
+}
+
+#[test]
+fn parse_args_from_named_returns_none_on_failure() {
+    let opts = Options::new();
+    let args = vec!["--bogus".to_string()];
+
+    // No assertion on stderr here (this crate has no test seam for it),
+    // but this at least exercises the `program`-qualified path that
+    // `dispatch` relies on and confirms it still reports failure as `None`.
+    assert!(super::parse_args_from_named(&opts, "cargo build", args.into_iter()).is_none());
+}
+
+#[test]
+fn dispatch_rejects_unknown_verb() {
+    let opts = Options::new();
+    let subcommands = [("build", &opts)];
+
+    let args = vec!["frobnicate".to_string()];
+    assert!(super::dispatch("cargo", &subcommands, args.into_iter()).is_none());
+}
+
+#[test]
+fn color_resolve_defaults_to_auto() {
+    let mut opts = Options::new();
+    color::coloropt(&mut opts);
+    let matches = opts.parse(&[][]).unwrap();
+
+    assert!(color::resolve(&matches) == Color::Auto);
+}
+
+#[test]
+fn color_resolve_honors_explicit_flag() {
+    let mut opts = Options::new();
+    color::coloropt(&mut opts);
+    let matches = opts.parse(&["--color".to_string(), "always".to_string()][]).unwrap();
+
+    assert!(color::resolve(&matches) == Color::Always);
+}
+
+#[test]
+fn verbosity_resolve_defaults_to_normal() {
+    let mut opts = Options::new();
+    verbosity::verboseopt(&mut opts);
+    verbosity::quietopt(&mut opts);
+    let matches = opts.parse(&[][]).unwrap();
+
+    assert!(verbosity::resolve(&matches) == Verbosity::Normal);
+}
+
+#[test]
+fn verbosity_resolve_quiet_wins_over_verbose() {
+    let mut opts = Options::new();
+    verbosity::verboseopt(&mut opts);
+    verbosity::quietopt(&mut opts);
+    let matches = opts.parse(&["-v".to_string(), "-q".to_string()][]).unwrap();
+
+    assert!(verbosity::resolve(&matches) == Verbosity::Quiet);
+}
+
+#[test]
+fn unstable_check_rejects_without_unlock() {
+    let mut unstable_opts = UnstableOpts::new();
+    unstable::unstable_optflag(&mut unstable_opts, "experimental-thing", "An experimental thing");
+    let mut opts = Options::new();
+    unstable::unstableopt(&mut opts);
+
+    let args = vec!["--experimental-thing".to_string()];
+    let (remaining, present) = unstable::extract(&unstable_opts, &args[]);
+    let matches = opts.parse(&remaining[]).unwrap();
+
+    assert!(unstable::check(&present[], &matches).is_err());
+}
+
+#[test]
+fn unstable_check_allows_with_unlock() {
+    let mut unstable_opts = UnstableOpts::new();
+    unstable::unstable_optflag(&mut unstable_opts, "experimental-thing", "An experimental thing");
+    let mut opts = Options::new();
+    unstable::unstableopt(&mut opts);
+
+    let args = vec!["-Z".to_string(), "--experimental-thing".to_string()];
+    let (remaining, present) = unstable::extract(&unstable_opts, &args[]);
+    let matches = opts.parse(&remaining[]).unwrap();
+
+    assert!(unstable::check(&present[], &matches).is_ok());
+}
+
+#[test]
+fn unstable_optflag_is_hidden_from_usage_string_when_locked() {
+    let mut unstable_opts = UnstableOpts::new();
+    unstable::unstable_optflag(&mut unstable_opts, "experimental-thing", "An experimental thing");
+    let mut opts = Options::new();
+    unstable::unstableopt(&mut opts);
+
+    let args: Vec<String> = Vec::new();
+    let (remaining, present) = unstable::extract(&unstable_opts, &args[]);
+    let matches = opts.parse(&remaining[]).unwrap();
+
+    assert!(present.is_empty());
+    assert!(!super::usage_string(&opts).contains("experimental-thing"));
+
+    let locked_usage = unstable::usage_string(&opts, &unstable_opts, &matches);
+    assert!(!locked_usage.contains("experimental-thing"));
+}
+
+#[test]
+fn unstable_usage_string_lists_entries_once_when_unlocked() {
+    let mut unstable_opts = UnstableOpts::new();
+    unstable::unstable_optflag(&mut unstable_opts, "experimental-thing", "An experimental thing");
+    let mut opts = Options::new();
+    unstable::unstableopt(&mut opts);
+
+    let args = vec!["-Z".to_string()];
+    let (remaining, _present) = unstable::extract(&unstable_opts, &args[]);
+    let matches = opts.parse(&remaining[]).unwrap();
+
+    let unlocked_usage = unstable::usage_string(&opts, &unstable_opts, &matches);
+    assert_eq!(unlocked_usage.matches("experimental-thing").count(), 1);
+}
+
+#[test]
+fn timer_resolve_reads_time_passes_flag() {
+    let mut opts = Options::new();
+    timer::timeopt(&mut opts);
+    let matches = opts.parse(&["--time-passes".to_string()][]).unwrap();
+
+    assert!(timer::enabled(&matches));
+}
+
+#[test]
+fn timer_disabled_guard_does_not_panic_on_drop() {
+    let _guard = Timer::pass("disabled-pass", false);
+}
+
+#[test]
+fn timer_rss_field_reports_real_delta_when_both_samples_succeed() {
+    assert_eq!(timer::rss_field(Some(100), Some(150)), "50".to_string());
+}
+
+#[test]
+fn timer_rss_field_degrades_to_dash_when_wholly_unavailable() {
+    assert_eq!(timer::rss_field(None, None), "-".to_string());
+}
+
+#[test]
+fn timer_rss_field_is_unknown_rather_than_zero_on_partial_failure() {
+    assert_eq!(timer::rss_field(Some(100), None), "?".to_string());
+    assert_eq!(timer::rss_field(None, Some(100)), "?".to_string());
+}
@@ -0,0 +1,124 @@
+//! `--time-passes`-style timing and memory-usage reporting.
+//!
+//! A program registers `timeopt` and resolves the parsed flag with
+//! `enabled`, then wraps each phase it wants profiled in a `Timer::pass`
+//! guard. When disabled, constructing and dropping a guard costs nothing
+//! beyond the `bool` check; when enabled, dropping it prints one line to
+//! stderr with the phase's elapsed time and change in resident set size.
+
+use getopts::{Matches, Options};
+use std::old_io;
+use std::old_io::Reader;
+use std::old_io::fs::File;
+use time;
+
+extern {
+    fn sysconf(name: i32) -> i64;
+}
+
+/// `_SC_PAGESIZE`, per Linux's `bits/confname.h`.
+const SC_PAGESIZE: i32 = 30;
+
+/// Add a `--time-passes` flag to `Options`.
+pub fn timeopt(opts: &mut Options) -> &mut Options {
+    opts.optflag("", "time-passes", "Print timing and memory info for each pass")
+}
+
+/// Resolve the matched `--time-passes` flag (as registered by `timeopt`)
+/// into whether `Timer` guards should actually print anything.
+pub fn enabled(matches: &Matches) -> bool {
+    matches.opt_present("time-passes")
+}
+
+/// An RAII guard that times and memory-profiles a single named pass.
+///
+/// Construct one with `Timer::pass` at the start of the phase being
+/// measured. On drop, if `enabled`, it prints a single tab-separated line
+/// to stderr of the form:
+///
+/// ```ignore
+/// time: <secs>\t<rss_delta_bytes>\t<pass_name>
+/// ```
+///
+/// The RSS field degrades to `-` when resident-set size couldn't be read
+/// at either end of the measurement (the expected case wherever
+/// `/proc/self/statm` doesn't exist), or to `?` when it was readable at
+/// only one end (an actual read failure partway through, which shouldn't
+/// be confused with a genuine zero-byte delta). See `rss_field`.
+///
+/// Nested guards each print on their own drop, so a program can annotate
+/// distinct phases independently.
+pub struct Timer {
+    name: String,
+    enabled: bool,
+    start_ns: u64,
+    start_rss: Option<u64>,
+}
+
+impl Timer {
+    /// Start timing a pass named `name`. Callers can construct a `Timer`
+    /// unconditionally and let `enabled` decide whether anything is ever
+    /// printed, so `--time-passes` support costs nothing when it's off.
+    pub fn pass(name: &str, enabled: bool) -> Timer {
+        Timer {
+            name: if enabled { name.to_string() } else { String::new() },
+            enabled: enabled,
+            start_ns: if enabled { time::precise_time_ns() } else { 0 },
+            start_rss: if enabled { resident_set_size() } else { None },
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        let elapsed_secs = (time::precise_time_ns() - self.start_ns) as f64 / 1_000_000_000.0;
+        let rss_field = rss_field(self.start_rss, resident_set_size());
+        let line = format!("time: {:.3}\t{}\t{}\n", elapsed_secs, rss_field, self.name);
+        let _ = old_io::stderr().write_str(&line[]);
+    }
+}
+
+/// Render the RSS column of a `Timer`'s output line from the resident set
+/// size sampled at the start and end of the pass.
+///
+/// A real delta is only printed when both samples succeeded. `(None, None)`
+/// -- resident-set size unavailable for the whole pass, the expected
+/// degrade path wherever `/proc/self/statm` doesn't exist -- renders as
+/// `-`. Anything else (exactly one sample missing) means a read that
+/// should have worked didn't; rendering that as `0` would be
+/// indistinguishable from a genuinely unchanged RSS, so it renders as `?`
+/// instead.
+pub fn rss_field(start: Option<u64>, end: Option<u64>) -> String {
+    match (start, end) {
+        (Some(start), Some(end)) => (end as i64 - start as i64).to_string(),
+        (None, None) => "-".to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+/// Read the process's current resident set size, in bytes.
+///
+/// Parses `/proc/self/statm` on Linux (the second field is resident pages,
+/// multiplied by the page size). Returns `None` wherever that file isn't
+/// available, in which case `Timer` degrades gracefully to reporting only
+/// elapsed time.
+fn resident_set_size() -> Option<u64> {
+    let mut file = match File::open(&Path::new("/proc/self/statm")) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+    let contents = match file.read_to_string() {
+        Ok(contents) => contents,
+        Err(_) => return None,
+    };
+    let resident_pages: Option<u64> = contents.split(' ').nth(1).and_then(|s| s.parse().ok());
+    resident_pages.map(|pages| pages * page_size())
+}
+
+fn page_size() -> u64 {
+    let size = unsafe { sysconf(SC_PAGESIZE) };
+    if size > 0 { size as u64 } else { 4096 }
+}
@@ -0,0 +1,97 @@
+//! Unstable/experimental flag gating, `-Z`-style.
+//!
+//! A program records experimental flags in an `UnstableOpts` via
+//! `unstable_optflag` instead of adding them to its `getopts::Options`
+//! directly -- `getopts` has no notion of a hidden option, so registering
+//! them there would make them show up in `cli::usage_string` and be
+//! accepted regardless of `-Z`. Instead, `extract` pulls matching tokens out
+//! of the raw argument list before `getopts` ever sees it, and `check`
+//! rejects their use unless `-Z` was also given.
+
+use getopts::{Matches, Options};
+
+/// A registry of unstable options, kept separate from a program's stable
+/// `Options` so their flags never reach `getopts::Options::parse` or
+/// `getopts::Options::usage` until unlocked.
+pub struct UnstableOpts {
+    entries: Vec<(String, String)>,
+}
+
+impl UnstableOpts {
+    pub fn new() -> UnstableOpts {
+        UnstableOpts { entries: Vec::new() }
+    }
+}
+
+/// Add the `-Z` unlock flag to `opts`. Passing `-Z` permits use of any flag
+/// registered via `unstable_optflag`.
+///
+/// Unlike the entries recorded by `unstable_optflag`, `-Z` itself is a
+/// stable, documented flag, so it's registered normally and will appear in
+/// `cli::usage_string`.
+pub fn unstableopt(opts: &mut Options) -> &mut Options {
+    opts.optflag("Z", "", "Unlock unstable options")
+}
+
+/// Record an unstable boolean flag named `long_name` in `unstable`.
+///
+/// This does *not* add the flag to any `getopts::Options` -- see `extract`,
+/// which must run over the raw argument list before it's handed to
+/// `getopts::Options::parse`.
+pub fn unstable_optflag(unstable: &mut UnstableOpts, long_name: &str, desc: &str) {
+    unstable.entries.push((long_name.to_string(), desc.to_string()));
+}
+
+/// Pull any `--<long_name>` token registered in `unstable` out of `args`.
+///
+/// Returns the remaining arguments -- safe to hand to
+/// `getopts::Options::parse`, which was never told about these flags and
+/// would otherwise fail with "unrecognized option" -- together with the
+/// subset of registered long names that were found.
+pub fn extract(unstable: &UnstableOpts, args: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut present = Vec::new();
+    let mut remaining = Vec::new();
+    for arg in args.iter() {
+        let matched = unstable.entries.iter()
+            .find(|&&(ref name, _)| *arg == format!("--{}", name));
+        match matched {
+            Some(&(ref name, _)) => present.push(name.clone()),
+            None => remaining.push(arg.clone()),
+        }
+    }
+    (remaining, present)
+}
+
+/// Check for unstable flags used without the `-Z` unlock.
+///
+/// `present` is the list of registered long names found by `extract`.
+/// Returns `Err` describing the offending flags if `present` is non-empty
+/// and `matches` doesn't show `-Z`; callers should write the message to
+/// stderr and set the exit status via
+/// `cli::die(cli::sysexits::USAGE, &msg[])`. Returns `Ok(())` otherwise.
+pub fn check(present: &[String], matches: &Matches) -> Result<(), String> {
+    if matches.opt_present("Z") || present.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "the following flags are unstable and require -Z to unlock: {}",
+            present.connect(", ")
+        ))
+    }
+}
+
+/// Construct a usage string for `stable_opts`, appending the entries in
+/// `unstable` under their own heading only when `matches` shows `-Z` was
+/// given. With the unlock absent, this is identical to `cli::usage_string`,
+/// since `stable_opts` was never told about the unstable entries in the
+/// first place.
+pub fn usage_string(stable_opts: &Options, unstable: &UnstableOpts, matches: &Matches) -> String {
+    let mut usage = ::usage_string(stable_opts);
+    if matches.opt_present("Z") && !unstable.entries.is_empty() {
+        usage.push_str("\nUnstable options (-Z):\n");
+        for &(ref name, ref desc) in unstable.entries.iter() {
+            usage.push_str(&format!("    --{:<20} {}\n", name, desc)[]);
+        }
+    }
+    usage
+}
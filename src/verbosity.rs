@@ -0,0 +1,66 @@
+//! Verbosity levels as a first-class concept.
+//!
+//! A program registers `verboseopt` and `quietopt`, resolves the matched
+//! flags with `resolve` into a single `Verbosity`, and gates its own output
+//! on it via `vprintln`/`vprintln_verbose` instead of hand-rolling
+//! `opt_present` checks at every call site. `-v`/`-q` are deliberately free
+//! for this purpose: `cli::versionopt` avoids `-v` and `-V` precisely so
+//! verbose output can claim `-v`.
+
+use getopts::{Matches, Options};
+
+/// How chatty a program's diagnostic output should be.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Suppress everything but essential output.
+    Quiet,
+    /// The default: ordinary chatter, no extra detail.
+    Normal,
+    /// Ordinary chatter plus extra diagnostic detail.
+    Verbose,
+}
+
+/// Add a `-v`/`--verbose` flag to `Options`.
+pub fn verboseopt(opts: &mut Options) -> &mut Options {
+    opts.optflag("v", "verbose", "Use verbose output")
+}
+
+/// Add a `-q`/`--quiet` flag to `Options`.
+pub fn quietopt(opts: &mut Options) -> &mut Options {
+    opts.optflag("q", "quiet", "Suppress non-essential output")
+}
+
+/// Resolve the matched `-v`/`-q` flags (as registered by `verboseopt` and
+/// `quietopt`) into a single `Verbosity`.
+///
+/// `-q` takes precedence over `-v` if both are somehow given, since
+/// suppressing output is the safer failure mode.
+pub fn resolve(matches: &Matches) -> Verbosity {
+    if matches.opt_present("q") {
+        Verbosity::Quiet
+    } else if matches.opt_present("v") {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    }
+}
+
+/// Print `msg` to stdout unless `verbosity` is `Verbosity::Quiet`.
+///
+/// Lets programs gate their ordinary chatter on verbosity without
+/// hand-rolling `matches.opt_present("q")` checks at every call site.
+pub fn vprintln(verbosity: Verbosity, msg: &str) {
+    if verbosity != Verbosity::Quiet {
+        println!("{}", msg);
+    }
+}
+
+/// Print `msg` to stdout only when `verbosity` is `Verbosity::Verbose`.
+///
+/// Lets programs gate extra diagnostic detail behind `-v` without
+/// hand-rolling `matches.opt_present("v")` checks at every call site.
+pub fn vprintln_verbose(verbosity: Verbosity, msg: &str) {
+    if verbosity == Verbosity::Verbose {
+        println!("{}", msg);
+    }
+}